@@ -16,9 +16,28 @@ pub enum Command {
     Del(Vec<String>),
     Incr(String),
     Decr(String),
+    Auth(String),
     Unknown(String),
 }
 
+/// Per-connection state that can't live on `Store` because it differs between
+/// clients sharing the same store, such as whether `AUTH` has succeeded yet.
+#[derive(Debug, Default)]
+pub struct ConnectionState {
+    pub authenticated: bool,
+}
+
+/// Compares two strings without short-circuiting on the first mismatched
+/// byte, so checking a client's `AUTH` password against `requirepass`
+/// doesn't leak how many leading bytes matched through response timing.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 impl Command {
     pub fn from_resp(resp: RespType) -> Option<Command> {
         match resp {
@@ -130,6 +149,15 @@ impl Command {
                             _ => None,
                         }
                     }
+                    "AUTH" => {
+                        if array.len() != 2 {
+                            return None;
+                        }
+                        match &array[1] {
+                            RespType::BulkString(Some(s)) => Some(Command::Auth(s.clone())),
+                            _ => None,
+                        }
+                    }
                     cmd => Some(Command::Unknown(cmd.to_string())),
                 }
             }
@@ -137,7 +165,46 @@ impl Command {
         }
     }
 
-    pub fn execute(&self, store: &Store) -> RespType {
+    /// Fills in `SET`'s expiry from the configured default policy when the
+    /// client didn't specify one, shared by every transport that executes
+    /// commands (plain TCP, TLS, WebSocket).
+    pub fn apply_default_expiry(&mut self, default_expiry: Option<Duration>) {
+        if let Command::Set { expiry, .. } = self {
+            if expiry.is_none() {
+                *expiry = default_expiry;
+            }
+        }
+    }
+
+    /// Execute the command against `store`, gating access on `state` when a
+    /// password is configured. `AUTH` and `PING` are always allowed through
+    /// so a client can authenticate (or probe liveness) before anything else.
+    pub fn execute(
+        &self,
+        store: &Store,
+        state: &mut ConnectionState,
+        requirepass: Option<&str>,
+    ) -> RespType {
+        if let Some(expected) = requirepass {
+            match self {
+                Command::Auth(given) => {
+                    return if constant_time_eq(given, expected) {
+                        state.authenticated = true;
+                        RespType::SimpleString("OK".to_string())
+                    } else {
+                        RespType::Error("ERR invalid password".to_string())
+                    };
+                }
+                Command::Ping => {}
+                _ if !state.authenticated => {
+                    return RespType::Error("NOAUTH Authentication required".to_string());
+                }
+                _ => {}
+            }
+        } else if let Command::Auth(_) = self {
+            return RespType::Error("ERR Client sent AUTH, but no password is set".to_string());
+        }
+
         match self {
             Command::Ping => RespType::SimpleString("PONG".to_string()),
             Command::Echo(msg) => RespType::BulkString(Some(msg.clone())),
@@ -169,7 +236,64 @@ impl Command {
                     Err(e) => RespType::Error(e.to_string()),
                 }
             }
+            Command::Auth(_) => unreachable!("AUTH is handled above before the password gate"),
             Command::Unknown(cmd) => RespType::Error(format!("ERR unknown command '{}'", cmd)),
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_password_authenticates_and_unlocks_commands() {
+        let store = Store::new();
+        let mut state = ConnectionState::default();
+
+        let response = Command::Auth("secret".to_string()).execute(&store, &mut state, Some("secret"));
+        assert_eq!(response, RespType::SimpleString("OK".to_string()));
+        assert!(state.authenticated);
+
+        let response = Command::Get("key".to_string()).execute(&store, &mut state, Some("secret"));
+        assert_eq!(response, RespType::BulkString(None));
+    }
+
+    #[test]
+    fn wrong_password_is_rejected_and_leaves_state_unauthenticated() {
+        let store = Store::new();
+        let mut state = ConnectionState::default();
+
+        let response = Command::Auth("wrong".to_string()).execute(&store, &mut state, Some("secret"));
+        assert_eq!(response, RespType::Error("ERR invalid password".to_string()));
+        assert!(!state.authenticated);
+    }
+
+    #[test]
+    fn unauthenticated_commands_other_than_auth_and_ping_are_refused() {
+        let store = Store::new();
+        let mut state = ConnectionState::default();
+
+        let response = Command::Get("key".to_string()).execute(&store, &mut state, Some("secret"));
+        assert_eq!(
+            response,
+            RespType::Error("NOAUTH Authentication required".to_string())
+        );
+
+        let response = Command::Ping.execute(&store, &mut state, Some("secret"));
+        assert_eq!(response, RespType::SimpleString("PONG".to_string()));
+    }
+
+    #[test]
+    fn auth_without_requirepass_configured_is_an_error() {
+        let store = Store::new();
+        let mut state = ConnectionState::default();
+
+        let response = Command::Auth("anything".to_string()).execute(&store, &mut state, None);
+        assert_eq!(
+            response,
+            RespType::Error("ERR Client sent AUTH, but no password is set".to_string())
+        );
+        assert!(!state.authenticated);
+    }
+}
\ No newline at end of file
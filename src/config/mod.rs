@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::fs;
+use tokio::sync::watch;
+
+/// Server configuration, loaded from a TOML file and reloaded on change.
+///
+/// Fields that only matter at startup (`bind_addr`, `port`, `tls`,
+/// `ws_bind_addr`) are read once when the listeners are created; fields that
+/// can change live (`requirepass`, `max_clients`) are re-applied to the
+/// running server each time the file is reloaded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub bind_addr: String,
+    pub port: u16,
+    #[serde(default = "default_max_clients")]
+    pub max_clients: usize,
+    #[serde(default)]
+    pub requirepass: Option<String>,
+    #[serde(default)]
+    pub default_expiry_secs: Option<u64>,
+    #[serde(default = "default_max_buffer_bytes")]
+    pub max_buffer_bytes: usize,
+    #[serde(default = "default_active_expire_interval_ms")]
+    pub active_expire_interval_ms: u64,
+    #[serde(default = "default_active_expire_sample_size")]
+    pub active_expire_sample_size: usize,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    #[serde(default = "default_ws_bind_addr")]
+    pub ws_bind_addr: String,
+}
+
+/// TLS listener settings. `enabled` is off by default; flipping it on (and
+/// pointing `cert_path`/`key_path` at a real certificate and key) switches
+/// the listener over to `--tls-port`-style encrypted connections.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    #[serde(default)]
+    pub key_path: Option<String>,
+}
+
+fn default_max_clients() -> usize {
+    10_000
+}
+
+fn default_max_buffer_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_active_expire_interval_ms() -> u64 {
+    100
+}
+
+fn default_active_expire_sample_size() -> usize {
+    20
+}
+
+fn default_ws_bind_addr() -> String {
+    "127.0.0.1:6380".to_string()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_addr: "127.0.0.1".to_string(),
+            port: 6379,
+            max_clients: default_max_clients(),
+            requirepass: None,
+            default_expiry_secs: None,
+            max_buffer_bytes: default_max_buffer_bytes(),
+            active_expire_interval_ms: default_active_expire_interval_ms(),
+            active_expire_sample_size: default_active_expire_sample_size(),
+            tls: TlsConfig::default(),
+            ws_bind_addr: default_ws_bind_addr(),
+        }
+    }
+}
+
+impl Config {
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.bind_addr, self.port)
+    }
+
+    pub fn default_expiry(&self) -> Option<Duration> {
+        self.default_expiry_secs.map(Duration::from_secs)
+    }
+
+    pub fn active_expire_interval(&self) -> Duration {
+        Duration::from_millis(self.active_expire_interval_ms)
+    }
+
+    fn load(path: &Path) -> Result<Config> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+}
+
+/// Loads the config at `path` and spawns a task that re-reads it whenever the
+/// file changes, pushing a fresh `Arc<Config>` through the returned
+/// `watch::Receiver`. `process_connection` reads the current config off this
+/// receiver per-command, so settings like `requirepass` and `max_clients`
+/// apply without restarting the server.
+pub fn watch(path: impl Into<PathBuf>) -> Result<watch::Receiver<Arc<Config>>> {
+    let path = path.into();
+    let initial = Config::load(&path)?;
+    let (tx, rx) = watch::channel(Arc::new(initial));
+
+    tokio::spawn(async move {
+        let mut last_modified = fs::metadata(&path).await.ok().and_then(|m| m.modified().ok());
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            let modified = match fs::metadata(&path).await.ok().and_then(|m| m.modified().ok()) {
+                Some(modified) => modified,
+                None => continue,
+            };
+
+            if Some(modified) == last_modified {
+                continue;
+            }
+            last_modified = Some(modified);
+
+            match Config::load(&path) {
+                Ok(config) => {
+                    println!("Reloaded config from {}", path.display());
+                    if tx.send(Arc::new(config)).is_err() {
+                        // No receivers left; the server is shutting down.
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("Failed to reload config: {}", e),
+            }
+        }
+    });
+
+    Ok(rx)
+}
@@ -1,42 +1,142 @@
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
 use tokio::net::{TcpListener, TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::sync::watch;
 use bytes::BytesMut;
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 mod resp;
 mod command;
 mod store;
+mod tls;
+mod config;
+#[cfg(feature = "websocket")]
+mod ws;
 
 use resp::RespType;
-use command::Command;
+use command::{Command, ConnectionState};
 use store::Store;
+use tls::TlsSettings;
+use config::Config;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let listener = TcpListener::bind("127.0.0.1:6379").await?;
-    println!("Redis server listening on port 6379");
+    let config_path = env::args().nth(1).unwrap_or_else(|| "redis.toml".to_string());
+    let config = config::watch(config_path)?;
+
+    let bind_addr = config.borrow().bind_address();
+    let listener = TcpListener::bind(&bind_addr).await?;
+    println!("Redis server listening on {}", bind_addr);
 
     let store = Store::new();
+    let active_clients = Arc::new(AtomicUsize::new(0));
+
+    let acceptor = if config.borrow().tls.enabled {
+        let tls_config = config.borrow().tls.clone();
+        let settings = TlsSettings {
+            cert_path: tls_config.cert_path.context("tls.enabled is true but tls.cert_path is unset")?,
+            key_path: tls_config.key_path.context("tls.enabled is true but tls.key_path is unset")?,
+        };
+        Some(tls::build_acceptor(&settings)?)
+    } else {
+        None
+    };
+
+    #[cfg(feature = "websocket")]
+    {
+        let ws_store = store.clone();
+        let ws_config = config.clone();
+        let ws_bind_addr = config.borrow().ws_bind_addr.clone();
+        tokio::spawn(async move {
+            if let Err(e) = ws::serve(&ws_bind_addr, ws_store, ws_config).await {
+                eprintln!("WebSocket gateway error: {}", e);
+            }
+        });
+    }
+
+    let expire_store = store.clone();
+    let expire_config = config.clone();
+    tokio::spawn(active_expire_loop(expire_store, expire_config));
 
     loop {
         let (socket, addr) = listener.accept().await?;
+
+        let max_clients = config.borrow().max_clients;
+        if active_clients.load(Ordering::SeqCst) >= max_clients {
+            eprintln!("Rejecting {}: max_clients ({}) reached", addr, max_clients);
+            continue;
+        }
+
         println!("Client connected from: {}", addr);
         let store = store.clone();
+        let acceptor = acceptor.clone();
+        let config = config.clone();
+        let active_clients = active_clients.clone();
+        active_clients.fetch_add(1, Ordering::SeqCst);
         tokio::spawn(async move {
-            if let Err(e) = process_connection(socket, store).await {
+            let result = match acceptor {
+                Some(acceptor) => match acceptor.accept(socket).await {
+                    Ok(tls_stream) => process_connection(tls_stream, store, config).await,
+                    Err(e) => Err(e.into()),
+                },
+                None => process_connection(socket, store, config).await,
+            };
+            if let Err(e) = result {
                 eprintln!("Error processing connection: {}", e);
             }
+            active_clients.fetch_sub(1, Ordering::SeqCst);
         });
     }
 }
 
-async fn process_connection(socket: TcpStream, store: Store) -> Result<()> {
-    let (reader, writer) = socket.into_split();
+/// Periodically samples `store` for expired keys so an abandoned key with a
+/// TTL is reclaimed even if no client ever touches it again. Mirrors Redis's
+/// adaptive expire cycle: if a sample comes back more than ~25% expired, run
+/// another pass immediately instead of waiting out the full interval. The
+/// inner loop is time-boxed to `interval` and yields between passes, so a
+/// keyspace that never drops below the 25% threshold can't turn this into a
+/// tight synchronous loop that starves every other task on the worker thread.
+async fn active_expire_loop(store: Store, config: watch::Receiver<Arc<Config>>) {
+    loop {
+        let (interval, sample_size) = {
+            let cfg = config.borrow();
+            (cfg.active_expire_interval(), cfg.active_expire_sample_size)
+        };
+
+        let budget_start = tokio::time::Instant::now();
+        loop {
+            let reaped = store.active_expire_cycle(sample_size);
+            if sample_size == 0 || reaped * 4 < sample_size {
+                break;
+            }
+            if budget_start.elapsed() >= interval {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn process_connection<S>(
+    socket: S,
+    store: Store,
+    config: watch::Receiver<Arc<Config>>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, writer) = tokio::io::split(socket);
     let mut reader = tokio::io::BufReader::new(reader);
     let mut writer = BufWriter::new(writer);
     let mut buffer = BytesMut::with_capacity(4096);
+    let mut state = ConnectionState::default();
 
-    loop {
+    'conn: loop {
         // Read data into the buffer
         match reader.read_buf(&mut buffer).await {
             Ok(0) => {
@@ -45,12 +145,42 @@ async fn process_connection(socket: TcpStream, store: Store) -> Result<()> {
                 break;
             }
             Ok(_) => {
+                let max_buffer_bytes = config.borrow().max_buffer_bytes;
+                if buffer.len() > max_buffer_bytes {
+                    let err = RespType::Error("ERR Protocol error: buffer limit exceeded".to_string());
+                    writer.write_all(&err.serialize()).await?;
+                    writer.flush().await?;
+                    break 'conn;
+                }
+
                 // Process all complete commands in the buffer
-                while let Some(resp) = RespType::parse(&mut buffer)? {
-                    if let Some(cmd) = Command::from_resp(resp) {
-                        let response = cmd.execute(&store);
-                        writer.write_all(&response.serialize()).await?;
-                        writer.flush().await?;
+                loop {
+                    match RespType::parse(&mut buffer) {
+                        Ok(Some(resp)) => {
+                            if let Some(mut cmd) = Command::from_resp(resp) {
+                                let cfg = config.borrow().clone();
+                                cmd.apply_default_expiry(cfg.default_expiry());
+                                let response =
+                                    cmd.execute(&store, &mut state, cfg.requirepass.as_deref());
+                                writer.write_all(&response.serialize()).await?;
+                                writer.flush().await?;
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(_) => {
+                            // Both `InvalidData` (a bogus length header) and
+                            // `InvalidUtf8` (a bulk string with bytes that
+                            // aren't valid as this server's String-typed
+                            // values) are the client's fault, not ours --
+                            // resync past the offending frame instead of
+                            // tearing down the connection with it.
+                            let err = RespType::Error("ERR Protocol error".to_string());
+                            writer.write_all(&err.serialize()).await?;
+                            writer.flush().await?;
+                            if !resp::resync(&mut buffer) {
+                                break;
+                            }
+                        }
                     }
                 }
             }
@@ -62,4 +192,4 @@ async fn process_connection(socket: TcpStream, store: Store) -> Result<()> {
     }
 
     Ok(())
-} 
\ No newline at end of file
+}
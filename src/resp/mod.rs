@@ -19,43 +19,39 @@ pub enum RespError {
     InvalidUtf8(#[from] std::str::Utf8Error),
 }
 
-impl RespType {
-    pub fn parse(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
-        if input.is_empty() {
-            return Ok(None);
-        }
+/// Upper bound on a bulk string's declared length, mirroring Redis's default
+/// `proto-max-bulk-len`. Rejects the header outright instead of trusting it
+/// enough to slice or size anything.
+const MAX_BULK_LEN: i64 = 512 * 1024 * 1024;
 
-        // Look for a complete command
-        if !input.windows(2).any(|w| w == b"\r\n") {
-            return Ok(None);
-        }
+/// Upper bound on an array's declared element count, mirroring Redis's
+/// multibulk limit. Rejects the header before `Vec::with_capacity` ever sees
+/// an attacker-controlled size.
+const MAX_ARRAY_LEN: i64 = 1024 * 1024;
 
-        match input[0] as char {
-            '+' => parse_simple_string(input),
-            '-' => parse_error(input),
-            ':' => parse_integer(input),
-            '$' => parse_bulk_string(input),
-            '*' => parse_array(input),
-            _ => {
-                // Handle plain text commands (redis-cli without raw mode)
-                if let Some(end) = find_crlf(input) {
-                    let line = str::from_utf8(&input[..end])?;
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.is_empty() {
-                        return Err(RespError::InvalidData);
-                    }
-
-                    let mut array = Vec::new();
-                    for part in parts {
-                        array.push(RespType::BulkString(Some(part.to_string())));
-                    }
-
-                    input.advance(end + 2); // Skip CRLF
-                    Ok(Some(RespType::Array(Some(array))))
-                } else {
-                    Ok(None)
-                }
+/// Upper bound on array nesting depth, mirroring Redis's own multibulk
+/// nesting limit. Without this, arrays-of-arrays recurse through
+/// `parse_at`/`parse_typed` one stack frame per level, and a few hundred
+/// thousand levels of `*1\r\n` (well under `max_buffer_bytes`) overflow the
+/// stack and abort the whole process, not just the offending connection.
+const MAX_NESTING_DEPTH: usize = 32;
+
+impl RespType {
+    /// Parses a single complete RESP value off the front of `input`, leaving
+    /// the buffer untouched if a full value isn't available yet.
+    ///
+    /// This walks the bytes with an index cursor rather than mutating `input`
+    /// mid-parse, so a partial frame (including one nested deep inside an
+    /// array) never desyncs the buffer: `input` is only advanced once
+    /// `parse_at` reports a complete top-level value.
+    pub fn parse(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
+        let mut pos = 0;
+        match parse_at(input, &mut pos, 0)? {
+            Some(value) => {
+                input.advance(pos);
+                Ok(Some(value))
             }
+            None => Ok(None),
         }
     }
 
@@ -78,112 +74,260 @@ impl RespType {
     }
 }
 
-fn parse_simple_string(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
-    if let Some(end) = find_crlf(input) {
-        let line = str::from_utf8(&input[1..end])?.to_string();
-        input.advance(end + 2);
-        Ok(Some(RespType::SimpleString(line)))
-    } else {
-        Ok(None)
+/// Recovers from a protocol error by discarding bytes up to the next
+/// plausible frame start: a `*`, `$`, `+`, `-`, or `:` immediately following a
+/// `\r\n`. Returns `true` and advances `buf` past the garbage if one was
+/// found, `false` if the buffer holds no such marker yet (the caller should
+/// wait for more data rather than spin).
+pub fn resync(buf: &mut BytesMut) -> bool {
+    if buf.len() < 3 {
+        return false;
     }
-}
 
-fn parse_error(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
-    if let Some(end) = find_crlf(input) {
-        let line = str::from_utf8(&input[1..end])?.to_string();
-        input.advance(end + 2);
-        Ok(Some(RespType::Error(line)))
-    } else {
-        Ok(None)
+    for i in 0..buf.len() - 2 {
+        if &buf[i..i + 2] == b"\r\n" && matches!(buf[i + 2], b'*' | b'$' | b'+' | b'-' | b':') {
+            buf.advance(i + 2);
+            return true;
+        }
     }
-}
 
-fn parse_integer(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
-    if let Some(end) = find_crlf(input) {
-        let num_str = str::from_utf8(&input[1..end])?;
-        let num = num_str.parse::<i64>().map_err(|_| RespError::InvalidData)?;
-        input.advance(end + 2);
-        Ok(Some(RespType::Integer(num)))
-    } else {
-        Ok(None)
-    }
+    false
 }
 
-fn parse_bulk_string(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
-    if let Some(len_end) = find_crlf(input) {
-        let len_str = str::from_utf8(&input[1..len_end])?;
-        let len = len_str.parse::<i64>().map_err(|_| RespError::InvalidData)?;
+/// Scans `buf` for the next `\r\n` starting at `*pos`, returning the line
+/// (excluding the CRLF) and advancing `*pos` past it. Returns `None` without
+/// touching `*pos` if the buffer doesn't contain a complete line yet.
+fn read_line<'a>(buf: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let rest = buf.get(*pos..)?;
+    let end = rest.windows(2).position(|w| w == b"\r\n")?;
+    let line = &rest[..end];
+    *pos += end + 2;
+    Some(line)
+}
 
-        if len == -1 {
-            input.advance(len_end + 2);
-            return Ok(Some(RespType::BulkString(None)));
-        }
+/// Parses one RESP value out of `buf` starting at `*pos`, advancing `*pos`
+/// past it on success. Returns `Ok(None)` as soon as a read would run past
+/// `buf.len()`, leaving `*pos` wherever it happened to land -- callers must
+/// not act on `*pos` unless this returns `Ok(Some(_))`. `depth` counts how
+/// many arrays enclose this value; it's rejected once it passes
+/// `MAX_NESTING_DEPTH` instead of recursing further.
+fn parse_at(buf: &[u8], pos: &mut usize, depth: usize) -> Result<Option<RespType>, RespError> {
+    if depth > MAX_NESTING_DEPTH {
+        return Err(RespError::InvalidData);
+    }
 
-        let len = len as usize;
-        let total_len = len_end + 2 + len + 2;
+    let tag = match buf.get(*pos) {
+        Some(&b) => b as char,
+        None => return Ok(None),
+    };
 
-        if input.len() < total_len {
-            return Ok(None);
+    match tag {
+        '+' | '-' | ':' | '$' | '*' => {
+            *pos += 1;
+            parse_typed(tag, buf, pos, depth)
         }
-
-        let string = str::from_utf8(&input[len_end + 2..len_end + 2 + len])?.to_string();
-        input.advance(total_len);
-        Ok(Some(RespType::BulkString(Some(string))))
-    } else {
-        Ok(None)
+        // Handle plain text commands (redis-cli without raw mode)
+        _ => parse_inline(buf, pos),
     }
 }
 
-fn parse_array(input: &mut BytesMut) -> Result<Option<RespType>, RespError> {
-    if let Some(len_end) = find_crlf(input) {
-        let len_str = str::from_utf8(&input[1..len_end])?;
-        let len = len_str.parse::<i64>().map_err(|_| RespError::InvalidData)?;
+fn parse_typed(tag: char, buf: &[u8], pos: &mut usize, depth: usize) -> Result<Option<RespType>, RespError> {
+    let line = match read_line(buf, pos) {
+        Some(line) => line,
+        None => return Ok(None),
+    };
 
-        if len == -1 {
-            input.advance(len_end + 2);
-            return Ok(Some(RespType::Array(None)));
+    match tag {
+        '+' => Ok(Some(RespType::SimpleString(str::from_utf8(line)?.to_string()))),
+        '-' => Ok(Some(RespType::Error(str::from_utf8(line)?.to_string()))),
+        ':' => {
+            let n = str::from_utf8(line)?
+                .parse::<i64>()
+                .map_err(|_| RespError::InvalidData)?;
+            Ok(Some(RespType::Integer(n)))
         }
+        '$' => {
+            let len = str::from_utf8(line)?
+                .parse::<i64>()
+                .map_err(|_| RespError::InvalidData)?;
+            if len == -1 {
+                return Ok(Some(RespType::BulkString(None)));
+            }
+            if !(0..=MAX_BULK_LEN).contains(&len) {
+                return Err(RespError::InvalidData);
+            }
 
-        let len = len as usize;
-        let mut pos = len_end + 2;
-        let mut elements = Vec::with_capacity(len);
-
-        for _ in 0..len {
-            if pos >= input.len() {
+            let len = len as usize;
+            if buf.len() < *pos + len + 2 {
                 return Ok(None);
             }
 
-            let mut rest = input.split_off(pos);
-            std::mem::swap(input, &mut rest);
+            let s = str::from_utf8(&buf[*pos..*pos + len])?.to_string();
+            *pos += len + 2;
+            Ok(Some(RespType::BulkString(Some(s))))
+        }
+        '*' => {
+            let len = str::from_utf8(line)?
+                .parse::<i64>()
+                .map_err(|_| RespError::InvalidData)?;
+            if len == -1 {
+                return Ok(Some(RespType::Array(None)));
+            }
+            if !(0..=MAX_ARRAY_LEN).contains(&len) {
+                return Err(RespError::InvalidData);
+            }
 
-            match RespType::parse(input)? {
-                Some(element) => {
-                    pos = input.len();
-                    elements.push(element);
-                    let mut rest = rest;
-                    rest.unsplit(input.clone());
-                    *input = rest;
-                }
-                None => {
-                    let mut rest = rest;
-                    rest.unsplit(input.clone());
-                    *input = rest;
-                    return Ok(None);
+            let len = len as usize;
+            let mut elements = Vec::with_capacity(len);
+            for _ in 0..len {
+                match parse_at(buf, pos, depth + 1)? {
+                    Some(element) => elements.push(element),
+                    None => return Ok(None),
                 }
             }
+
+            Ok(Some(RespType::Array(Some(elements))))
         }
+        _ => unreachable!("parse_at only dispatches here for +-:$*"),
+    }
+}
 
-        Ok(Some(RespType::Array(Some(elements))))
-    } else {
-        Ok(None)
+fn parse_inline(buf: &[u8], pos: &mut usize) -> Result<Option<RespType>, RespError> {
+    let line = match read_line(buf, pos) {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+
+    let line = str::from_utf8(line)?;
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.is_empty() {
+        return Err(RespError::InvalidData);
     }
+
+    let array = parts
+        .into_iter()
+        .map(|part| RespType::BulkString(Some(part.to_string())))
+        .collect();
+    Ok(Some(RespType::Array(Some(array))))
 }
 
-fn find_crlf(input: &[u8]) -> Option<usize> {
-    if input.len() < 2 {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_array() {
+        let mut buf = BytesMut::from(
+            "*2\r\n*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n$4\r\nPING\r\n".as_bytes(),
+        );
+
+        let value = RespType::parse(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            value,
+            RespType::Array(Some(vec![
+                RespType::Array(Some(vec![
+                    RespType::BulkString(Some("foo".to_string())),
+                    RespType::BulkString(Some("bar".to_string())),
+                ])),
+                RespType::BulkString(Some("PING".to_string())),
+            ]))
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn partial_frame_leaves_buffer_untouched() {
+        let full = b"*1\r\n$5\r\nhello\r\n";
+        let mut buf = BytesMut::from(&full[..full.len() - 3]);
+
+        assert!(RespType::parse(&mut buf).unwrap().is_none());
+        assert_eq!(&buf[..], &full[..full.len() - 3]);
+
+        buf.extend_from_slice(&full[full.len() - 3..]);
+        let value = RespType::parse(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            value,
+            RespType::Array(Some(vec![RespType::BulkString(Some("hello".to_string()))]))
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn partial_frame_nested_inside_array_does_not_desync() {
+        // The inner bulk string is incomplete; a splicing parser could lose
+        // the array's length header here.
+        let mut buf = BytesMut::from("*2\r\n$3\r\nfoo\r\n$3\r\nba".as_bytes());
+        assert!(RespType::parse(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"r\r\n");
+        let value = RespType::parse(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            value,
+            RespType::Array(Some(vec![
+                RespType::BulkString(Some("foo".to_string())),
+                RespType::BulkString(Some("bar".to_string())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_bulk_string_length() {
+        let mut buf = BytesMut::from("$999999999999\r\n".as_bytes());
+        assert!(matches!(
+            RespType::parse(&mut buf),
+            Err(RespError::InvalidData)
+        ));
     }
 
-    input.windows(2)
-        .position(|window| window == b"\r\n")
-} 
\ No newline at end of file
+    #[test]
+    fn rejects_oversized_array_length() {
+        let mut buf = BytesMut::from("*999999999999\r\n".as_bytes());
+        assert!(matches!(
+            RespType::parse(&mut buf),
+            Err(RespError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn rejects_negative_array_length_other_than_minus_one() {
+        let mut buf = BytesMut::from("*-2\r\n".as_bytes());
+        assert!(matches!(
+            RespType::parse(&mut buf),
+            Err(RespError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn rejects_excessive_nesting_depth() {
+        let mut buf = BytesMut::from("*1\r\n".repeat(MAX_NESTING_DEPTH + 2).as_bytes());
+        assert!(matches!(
+            RespType::parse(&mut buf),
+            Err(RespError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn resync_skips_to_next_plausible_frame() {
+        let mut buf = BytesMut::from("garbage\r\n+OK\r\n".as_bytes());
+        assert!(resync(&mut buf));
+        assert_eq!(
+            RespType::parse(&mut buf).unwrap().unwrap(),
+            RespType::SimpleString("OK".to_string())
+        );
+    }
+
+    #[test]
+    fn resync_reports_no_marker_found() {
+        let mut buf = BytesMut::from("garbage with no frame marker".as_bytes());
+        assert!(!resync(&mut buf));
+    }
+
+    #[test]
+    fn parses_plain_inline_command() {
+        let mut buf = BytesMut::from("PING\r\n".as_bytes());
+        assert_eq!(
+            RespType::parse(&mut buf).unwrap().unwrap(),
+            RespType::Array(Some(vec![RespType::BulkString(Some("PING".to_string()))]))
+        );
+    }
+}
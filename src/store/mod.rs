@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
@@ -35,12 +36,17 @@ impl Value {
 #[derive(Clone, Default)]
 pub struct Store {
     data: Arc<Mutex<HashMap<String, Value>>>,
+    // Rotating offset into the map for `active_expire_cycle`, so repeated
+    // cycles sweep across the whole key space instead of re-inspecting the
+    // same fixed prefix every time.
+    expire_cursor: Arc<AtomicUsize>,
 }
 
 impl Store {
     pub fn new() -> Self {
         Self {
             data: Arc::new(Mutex::new(HashMap::new())),
+            expire_cursor: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -77,6 +83,43 @@ impl Store {
         }
     }
 
+    /// Samples up to `sample_size` entries and removes those that have
+    /// expired, returning how many were reaped. `HashMap` iteration order is
+    /// stable between calls on the same table (it only reshuffles on
+    /// rehash), so instead of always inspecting the same fixed prefix, each
+    /// call rotates `expire_cursor` forward by the sample size it used --
+    /// like Redis's own expire-cycle cursor, this guarantees every key is
+    /// eventually visited instead of only ever the first `sample_size` of
+    /// them. Called on a timer by the active-expiration task in `main`,
+    /// which re-runs immediately if the reaped share is high.
+    pub fn active_expire_cycle(&self, sample_size: usize) -> usize {
+        let mut data = self.data.lock().unwrap();
+        let len = data.len();
+        if len == 0 || sample_size == 0 {
+            return 0;
+        }
+
+        let sample_size = sample_size.min(len);
+        let start = self.expire_cursor.load(Ordering::Relaxed) % len;
+
+        let expired: Vec<String> = data
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(sample_size)
+            .filter(|(_, value)| value.is_expired())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        self.expire_cursor.fetch_add(sample_size, Ordering::Relaxed);
+
+        let reaped = expired.len();
+        for key in expired {
+            data.remove(&key);
+        }
+        reaped
+    }
+
     pub fn del(&self, keys: &[String]) -> i64 {
         let mut data = self.data.lock().unwrap();
         let mut deleted = 0;
@@ -148,4 +191,38 @@ impl Store {
             Ok(-1)
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_expire_cycle_eventually_reaps_keys_outside_the_first_sample() {
+        let store = Store::new();
+        for i in 0..50 {
+            store.set(format!("key{i}"), "v".to_string(), Some(Duration::from_millis(0)));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+
+        let sample_size = 5;
+        let mut total_reaped = 0;
+        for _ in 0..50 {
+            total_reaped += store.active_expire_cycle(sample_size);
+        }
+
+        assert_eq!(
+            total_reaped, 50,
+            "rotating cursor should sweep every expired key, not just the first sample"
+        );
+    }
+
+    #[test]
+    fn active_expire_cycle_ignores_live_keys() {
+        let store = Store::new();
+        store.set("alive".to_string(), "v".to_string(), None);
+
+        assert_eq!(store.active_expire_cycle(10), 0);
+        assert!(store.exists("alive"));
+    }
 } 
\ No newline at end of file
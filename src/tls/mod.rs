@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rustls_pemfile::{certs, pkcs8_private_keys};
+use tokio_rustls::rustls::{self, Certificate, PrivateKey};
+use tokio_rustls::TlsAcceptor;
+
+/// Filesystem locations of the server certificate and private key used to
+/// build the `rustls::ServerConfig` when TLS is enabled.
+pub struct TlsSettings {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+/// Build a `TlsAcceptor` from a PEM-encoded certificate chain and private key.
+///
+/// The acceptor is cheap to clone (it wraps an `Arc` internally), so it can be
+/// cloned into each spawned connection task alongside the `Store`.
+pub fn build_acceptor(settings: &TlsSettings) -> Result<TlsAcceptor> {
+    let certs = load_certs(&settings.cert_path)?;
+    let key = load_private_key(&settings.key_path)?;
+
+    let config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("failed to build rustls server config")?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>> {
+    let file = File::open(Path::new(path))
+        .with_context(|| format!("failed to open certificate file {}", path))?;
+    let mut reader = BufReader::new(file);
+    let raw = certs(&mut reader)
+        .with_context(|| format!("failed to parse certificates in {}", path))?;
+    Ok(raw.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &str) -> Result<PrivateKey> {
+    let file = File::open(Path::new(path))
+        .with_context(|| format!("failed to open private key file {}", path))?;
+    let mut reader = BufReader::new(file);
+    let mut keys = pkcs8_private_keys(&mut reader)
+        .with_context(|| format!("failed to parse private key in {}", path))?;
+
+    keys.pop()
+        .map(PrivateKey)
+        .context("no PKCS#8 private key found in key file")
+}
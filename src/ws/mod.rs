@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use bytes::BytesMut;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::command::{Command, ConnectionState};
+use crate::config::Config;
+use crate::resp::{self, RespType};
+use crate::store::Store;
+
+/// Accepts WebSocket upgrades on `addr` and feeds each binary frame through
+/// the same RESP parse/execute pipeline as the plain TCP listener, so
+/// browser clients and relay tunnels can reach the store without a native
+/// socket. Spawns one task per upgraded connection, exactly like the TCP
+/// accept loop in `main`.
+pub async fn serve(addr: &str, store: Store, config: watch::Receiver<Arc<Config>>) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("WebSocket gateway listening on {}", addr);
+
+    loop {
+        let (socket, addr) = listener.accept().await?;
+        let store = store.clone();
+        let config = config.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(socket, store, config).await {
+                eprintln!("WebSocket connection from {} failed: {}", addr, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    socket: TcpStream,
+    store: Store,
+    config: watch::Receiver<Arc<Config>>,
+) -> Result<()> {
+    let ws_stream = tokio_tungstenite::accept_async(socket).await?;
+    let (mut sink, mut stream) = ws_stream.split();
+    let mut state = ConnectionState::default();
+    let mut buffer = BytesMut::new();
+
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        let data = match message {
+            Message::Binary(data) => data,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        buffer.extend_from_slice(&data);
+
+        let max_buffer_bytes = config.borrow().max_buffer_bytes;
+        if buffer.len() > max_buffer_bytes {
+            let err = RespType::Error("ERR Protocol error: buffer limit exceeded".to_string());
+            sink.send(Message::Binary(err.serialize())).await?;
+            break;
+        }
+
+        loop {
+            match RespType::parse(&mut buffer) {
+                Ok(Some(resp)) => {
+                    if let Some(mut cmd) = Command::from_resp(resp) {
+                        let cfg = config.borrow().clone();
+                        cmd.apply_default_expiry(cfg.default_expiry());
+                        let response = cmd.execute(&store, &mut state, cfg.requirepass.as_deref());
+                        sink.send(Message::Binary(response.serialize())).await?;
+                    }
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    // Both `InvalidData` (a bogus length header) and
+                    // `InvalidUtf8` (a bulk string with bytes that aren't
+                    // valid as this server's String-typed values) are the
+                    // client's fault, not ours -- resync past the offending
+                    // frame instead of tearing down the connection with it.
+                    let err = RespType::Error("ERR Protocol error".to_string());
+                    sink.send(Message::Binary(err.serialize())).await?;
+                    if !resp::resync(&mut buffer) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}